@@ -1,52 +1,132 @@
-fn process_json_output(output: &str) -> anyhow::Result<String> {
-    // Attempt to clean up any non-JSON prefixes/suffixes
-    let cleaned = output
-        .lines()
-        .filter(|line| line.trim_start().starts_with('{'))
-        .collect::<String>();
-    
-    // Parse and re-serialize to ensure valid JSON
-    let value: serde_json::Value = serde_json::from_str(&cleaned)?;
-    Ok(serde_json::to_string(&value)?)
-}
-
-fn convert_results_to_map(
-    results: Vec<(
-        Vec<u8>,
-        String, 
-        String,
-        chrono::NaiveDateTime,
-        String,
-        String,
-        Option<String>,
-    )>,
-) -> Result<HashMap<PaneUuid, Vec<PersistedAIBlock>>, diesel::result::Error> {
-    let mut pane_to_ai_blocks: HashMap<PaneUuid, Vec<PersistedAIBlock>> = HashMap::new();
-
-    for (uuid, exchange_id, conversation_id, start_ts, input, output, _working_directory) in results {
-        if let Err(error) = (|| -> anyhow::Result<()> {
-            // Process input and output JSON
-            let input = process_json_output(&input)?;
-            let output = process_json_output(&output)?;
-
-            let ai_block = PersistedAIBlock {
-                exchange_id: AIAgentExchangeId::try_from(exchange_id)?,
-                session_uuid: uuid.clone(),
-                output: serde_json::from_str(&output)?,
-                conversation_id: AIConversationId::try_from(conversation_id)?,
-                start_ts: Local.from_utc_datetime(&start_ts),
-                input: serde_json::from_str(&input)?,
-            };
-
-            pane_to_ai_blocks
-                .entry(PaneUuid(uuid))
-                .and_modify(|ai_blocks| ai_blocks.push(ai_block.clone()))
-                .or_insert(vec![ai_block]);
-            Ok(())
-        })() {
-            log::warn!("failed to read AI block from SQLite: {}", error);
-        }
-    }
-
-    Ok(pane_to_ai_blocks)
-}
+fn process_json_output(output: &str) -> anyhow::Result<String> {
+    // Locate the first JSON object/array opener and its matching final
+    // closer, rather than filtering to brace-prefixed lines. The old
+    // line-filter approach concatenated only lines starting with '{',
+    // silently corrupting multi-line pretty-printed JSON and JSON arrays.
+    let start = output
+        .find(['{', '['])
+        .ok_or_else(|| anyhow::anyhow!("no JSON object or array found in output"))?;
+    let closing = if output.as_bytes()[start] == b'{' { '}' } else { ']' };
+    let end = output
+        .rfind(closing)
+        .ok_or_else(|| anyhow::anyhow!("no matching '{}' found in output", closing))?;
+
+    // Parse and re-serialize to ensure valid JSON
+    let value: serde_json::Value = serde_json::from_str(&output[start..=end])?;
+    Ok(serde_json::to_string(&value)?)
+}
+
+fn convert_results_to_map(
+    results: Vec<(
+        Vec<u8>,
+        String,
+        String,
+        chrono::NaiveDateTime,
+        String,
+        String,
+        Option<String>,
+    )>,
+) -> Result<HashMap<PaneUuid, Vec<PersistedAIBlock>>, diesel::result::Error> {
+    let mut pane_to_ai_blocks: HashMap<PaneUuid, Vec<PersistedAIBlock>> = HashMap::new();
+
+    for (uuid, exchange_id, conversation_id, start_ts, input, output, _working_directory) in results {
+        if let Err(error) = (|| -> anyhow::Result<()> {
+            // Process input and output JSON
+            let input = process_json_output(&input)?;
+            let output = process_json_output(&output)?;
+
+            let ai_block = PersistedAIBlock {
+                exchange_id: AIAgentExchangeId::try_from(exchange_id)?,
+                session_uuid: uuid.clone(),
+                output: serde_json::from_str(&output)?,
+                conversation_id: AIConversationId::try_from(conversation_id)?,
+                start_ts: Local.from_utc_datetime(&start_ts),
+                input: serde_json::from_str(&input)?,
+            };
+
+            pane_to_ai_blocks
+                .entry(PaneUuid(uuid))
+                .and_modify(|ai_blocks| ai_blocks.push(ai_block.clone()))
+                .or_insert(vec![ai_block]);
+            Ok(())
+        })() {
+            log::warn!("failed to read AI block from SQLite: {}", error);
+        }
+    }
+
+    Ok(pane_to_ai_blocks)
+}
+
+/// Which step of the conversion a `ConversionError` was raised from.
+#[derive(Debug)]
+enum ConversionStage {
+    InputJson,
+    OutputJson,
+    IdParsing,
+}
+
+/// Records a single rejected row from `convert_results_to_map_checked`, so
+/// callers can tell how many blocks were lost and why instead of only
+/// seeing a warning in the logs.
+#[derive(Debug)]
+struct ConversionError {
+    exchange_id: String,
+    stage: ConversionStage,
+    message: String,
+}
+
+/// Like `convert_results_to_map`, but instead of logging and dropping rows
+/// that fail to parse, returns every `ConversionError` alongside the
+/// successfully converted blocks.
+fn convert_results_to_map_checked(
+    results: Vec<(
+        Vec<u8>,
+        String,
+        String,
+        chrono::NaiveDateTime,
+        String,
+        String,
+        Option<String>,
+    )>,
+) -> (HashMap<PaneUuid, Vec<PersistedAIBlock>>, Vec<ConversionError>) {
+    let mut pane_to_ai_blocks: HashMap<PaneUuid, Vec<PersistedAIBlock>> = HashMap::new();
+    let mut errors = Vec::new();
+
+    for (uuid, exchange_id, conversation_id, start_ts, input, output, _working_directory) in results {
+        let outcome = (|| -> Result<(PaneUuid, PersistedAIBlock), (ConversionStage, anyhow::Error)> {
+            let input = process_json_output(&input).map_err(|e| (ConversionStage::InputJson, e))?;
+            let output = process_json_output(&output).map_err(|e| (ConversionStage::OutputJson, e))?;
+
+            let ai_block = PersistedAIBlock {
+                exchange_id: AIAgentExchangeId::try_from(exchange_id.clone())
+                    .map_err(|e| (ConversionStage::IdParsing, e.into()))?,
+                session_uuid: uuid.clone(),
+                output: serde_json::from_str(&output).map_err(|e| (ConversionStage::OutputJson, e.into()))?,
+                conversation_id: AIConversationId::try_from(conversation_id.clone())
+                    .map_err(|e| (ConversionStage::IdParsing, e.into()))?,
+                start_ts: Local.from_utc_datetime(&start_ts),
+                input: serde_json::from_str(&input).map_err(|e| (ConversionStage::InputJson, e.into()))?,
+            };
+
+            Ok((PaneUuid(uuid), ai_block))
+        })();
+
+        match outcome {
+            Ok((pane_uuid, ai_block)) => {
+                pane_to_ai_blocks
+                    .entry(pane_uuid)
+                    .and_modify(|ai_blocks| ai_blocks.push(ai_block.clone()))
+                    .or_insert(vec![ai_block]);
+            }
+            Err((stage, error)) => {
+                errors.push(ConversionError {
+                    exchange_id,
+                    stage,
+                    message: error.to_string(),
+                });
+            }
+        }
+    }
+
+    (pane_to_ai_blocks, errors)
+}
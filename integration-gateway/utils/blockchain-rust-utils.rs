@@ -1,5 +1,22 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{BuildHasher, BuildHasherDefault};
 use serde::{Serialize, Deserialize};
+use twox_hash::XxHash64;
+
+/// Default hasher for the internal maps keyed by block id/hash. Those keys
+/// are already content hashes rather than attacker-chosen input, so the
+/// DoS-resistant (but slower) SipHash behind the standard library's
+/// `HashMap` is pure overhead here. Following Substrate's use of the
+/// xxHash/twox family for storage keys, `BlockResultIndex` defaults to this
+/// fast, non-cryptographic hasher and `convert_results_to_map` always uses
+/// it. Callers with untrusted keys can opt back into SipHash for the index by
+/// building it with `BlockResultIndex::<RandomState>::with_hasher`;
+/// `convert_results_to_map` has no such option.
+pub type FastHashBuilder = BuildHasherDefault<XxHash64>;
+
+/// A `BlockResult` map keyed by block ID, generic over its `BuildHasher` so
+/// callers aren't locked into [`FastHashBuilder`].
+pub type BlockResultMap<S = FastHashBuilder> = HashMap<String, BlockResult, S>;
 
 // Assuming these are the types used in your blockchain implementation
 // Replace with your actual types
@@ -7,11 +24,27 @@ use serde::{Serialize, Deserialize};
 pub struct BlockResult {
     pub id: String,
     pub hash: String,
+    pub parent_hash: String,
+    pub number: u64,
     pub timestamp: u64,
     pub data: Vec<u8>,
     pub metadata: Option<BlockMetadata>,
 }
 
+/// Identifies a block for lookup against a [`BlockResultIndex`].
+///
+/// Modeled after Parity/OpenEthereum's `BlockId`: callers can address a block
+/// by id, hash, or height, or ask for the chain tip/genesis without knowing
+/// its height up front.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockId {
+    Id(String),
+    Hash(String),
+    Number(u64),
+    Latest,
+    Earliest,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BlockMetadata {
     pub author: String,
@@ -31,13 +64,13 @@ pub struct BlockMetadata {
 /// # Returns
 /// 
 /// A HashMap where the keys are block IDs and the values are the corresponding BlockResult objects
-pub fn convert_results_to_map(results: Vec<BlockResult>) -> HashMap<String, BlockResult> {
-    let mut map = HashMap::with_capacity(results.len());
-    
+pub fn convert_results_to_map(results: Vec<BlockResult>) -> BlockResultMap {
+    let mut map = BlockResultMap::with_capacity_and_hasher(results.len(), FastHashBuilder::default());
+
     for result in results {
         map.insert(result.id.clone(), result);
     }
-    
+
     map
 }
 
@@ -119,48 +152,192 @@ where
     groups
 }
 
-/// Indexes block results for efficient multi-key lookup
-pub struct BlockResultIndex {
-    by_id: HashMap<String, BlockResult>,
-    by_hash: HashMap<String, String>, // Maps hash to ID
-    by_timestamp: HashMap<u64, Vec<String>>, // Maps timestamp to list of IDs
+/// Number of bytes backing each per-block [`Bloom`], following the fixed-size
+/// log-bloom design used for blockchain extras in OpenEthereum's client.
+const BLOOM_BYTES: usize = 256;
+const BLOOM_BITS: usize = BLOOM_BYTES * 8;
+const BLOOM_HASH_SEEDS: [u64; 3] = [0x9E3779B97F4A7C15, 0xC2B2AE3D27D4EB4F, 0x165667B19E3779F9];
+
+/// A fixed-size bit array used to pre-filter blocks by tagged field (author,
+/// version, ...) without scanning every block. A bloom never yields false
+/// negatives, so it is safe to use as a pre-filter ahead of an exact match.
+#[derive(Debug, Clone)]
+struct Bloom([u8; BLOOM_BYTES]);
+
+impl Bloom {
+    fn new() -> Self {
+        Self([0u8; BLOOM_BYTES])
+    }
+
+    fn bit_index(seed: u64, value: &str) -> usize {
+        use std::hash::{Hash, Hasher};
+        use std::collections::hash_map::DefaultHasher;
+
+        let mut hasher = DefaultHasher::new();
+        seed.hash(&mut hasher);
+        value.hash(&mut hasher);
+        (hasher.finish() as usize) % BLOOM_BITS
+    }
+
+    fn set_bit(&mut self, bit: usize) {
+        self.0[bit / 8] |= 1 << (bit % 8);
+    }
+
+    /// Sets the three bits derived from `value`, one per hash seed.
+    fn insert(&mut self, value: &str) {
+        for seed in BLOOM_HASH_SEEDS {
+            self.set_bit(Self::bit_index(seed, value));
+        }
+    }
+
+    /// True if every bit set in `self` is also set in `other` - i.e. `other`
+    /// is a plausible superset and may contain everything `self` tags.
+    fn is_subset_of(&self, other: &Bloom) -> bool {
+        self.0.iter().zip(other.0.iter()).all(|(mine, theirs)| mine & theirs == *mine)
+    }
+}
+
+/// The result of walking the parent-hash chain between two blocks, modeled on
+/// Parity's `TreeRoute`/`ImportRoute`: the blocks to retract (leave the `from`
+/// branch) and enact (join the `to` branch) to move from one to the other,
+/// plus their common ancestor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeRoute {
+    pub ancestor: String,
+    pub retracted: Vec<String>,
+    pub enacted: Vec<String>,
+}
+
+/// Indexes block results for efficient multi-key lookup.
+///
+/// Generic over `S: BuildHasher` so the internal maps can use a fast
+/// non-cryptographic hasher (see [`FastHashBuilder`]) since the keys are
+/// already content hashes, while still allowing `RandomState` when callers
+/// can't vouch for that.
+pub struct BlockResultIndex<S = FastHashBuilder> {
+    by_id: HashMap<String, BlockResult, S>,
+    by_hash: HashMap<String, String, S>, // Maps hash to ID
+    by_timestamp: BTreeMap<u64, Vec<String>>, // Maps timestamp to list of IDs, sorted for range queries
+    by_number: HashMap<u64, String, S>, // Maps block height to ID
+    min_number: Option<u64>,
+    max_number: Option<u64>,
+    blooms: HashMap<String, Bloom, S>, // Maps block ID to its tagged-field bloom
 }
 
-impl BlockResultIndex {
+impl BlockResultIndex<FastHashBuilder> {
+    /// Builds an index using the default [`FastHashBuilder`]. Lives on this
+    /// concrete impl (mirroring `HashMap::new`'s `impl HashMap<K, V,
+    /// RandomState>`) rather than the generic impl below, because a
+    /// defaulted type parameter on a *generic* impl is not used as an
+    /// inference fallback - `BlockResultIndex::new(results)` would otherwise
+    /// fail to infer `S`. Callers that need a different hasher should use
+    /// [`BlockResultIndex::with_hasher`].
     pub fn new(results: Vec<BlockResult>) -> Self {
-        let mut by_id = HashMap::with_capacity(results.len());
-        let mut by_hash = HashMap::with_capacity(results.len());
-        let mut by_timestamp = HashMap::new();
-        
+        Self::with_hasher(results)
+    }
+}
+
+impl Default for BlockResultIndex<FastHashBuilder> {
+    fn default() -> Self {
+        Self::with_hasher(Vec::new())
+    }
+}
+
+impl<S: BuildHasher + Default> BlockResultIndex<S> {
+    pub fn with_hasher(results: Vec<BlockResult>) -> Self {
+        let mut by_id = HashMap::with_capacity_and_hasher(results.len(), S::default());
+        let mut by_hash = HashMap::with_capacity_and_hasher(results.len(), S::default());
+        let mut by_timestamp = BTreeMap::new();
+        let mut by_number = HashMap::with_capacity_and_hasher(results.len(), S::default());
+        let mut min_number = None;
+        let mut max_number = None;
+        let mut blooms = HashMap::with_capacity_and_hasher(results.len(), S::default());
+
         for result in results {
             // Index by hash
             by_hash.insert(result.hash.clone(), result.id.clone());
-            
+
             // Index by timestamp
             by_timestamp.entry(result.timestamp)
                 .or_insert_with(Vec::new)
                 .push(result.id.clone());
-            
+
+            // Index by height
+            by_number.insert(result.number, result.id.clone());
+            min_number = Some(min_number.map_or(result.number, |min: u64| min.min(result.number)));
+            max_number = Some(max_number.map_or(result.number, |max: u64| max.max(result.number)));
+
+            // Index by tagged-field bloom
+            blooms.insert(result.id.clone(), Self::bloom_for(&result));
+
             // Index by ID (primary)
             by_id.insert(result.id.clone(), result);
         }
-        
+
         Self {
             by_id,
             by_hash,
             by_timestamp,
+            by_number,
+            min_number,
+            max_number,
+            blooms,
         }
     }
-    
+
+    fn bloom_for(result: &BlockResult) -> Bloom {
+        let mut bloom = Bloom::new();
+        if let Some(metadata) = &result.metadata {
+            bloom.insert(&metadata.author);
+            bloom.insert(&metadata.version);
+        }
+        bloom
+    }
+
+    /// Returns the blocks whose `metadata.author` equals `author`. Candidates
+    /// are selected by checking each block's bloom filter, and only those are
+    /// confirmed with an exact comparison, so the result contains no false
+    /// positives (and the bloom lookup itself never produces false negatives).
+    pub fn blocks_matching(&self, author: &str) -> Vec<&BlockResult> {
+        let mut query = Bloom::new();
+        query.insert(author);
+
+        self.blooms.iter()
+            .filter(|(_, bloom)| query.is_subset_of(bloom))
+            .filter_map(|(id, _)| self.by_id.get(id))
+            .filter(|result| {
+                result.metadata.as_ref().map_or(false, |metadata| metadata.author == author)
+            })
+            .collect()
+    }
+
     pub fn get_by_id(&self, id: &str) -> Option<&BlockResult> {
         self.by_id.get(id)
     }
-    
+
     pub fn get_by_hash(&self, hash: &str) -> Option<&BlockResult> {
         let id = self.by_hash.get(hash)?;
         self.by_id.get(id)
     }
-    
+
+    pub fn get_by_number(&self, number: u64) -> Option<&BlockResult> {
+        let id = self.by_number.get(&number)?;
+        self.by_id.get(id)
+    }
+
+    /// Resolves a [`BlockId`] to its block: a single entry point for lookups
+    /// by id, hash or height. Timestamp lookups still go through
+    /// `get_by_timestamp`.
+    pub fn get(&self, id: BlockId) -> Option<&BlockResult> {
+        match id {
+            BlockId::Id(id) => self.get_by_id(&id),
+            BlockId::Hash(hash) => self.get_by_hash(&hash),
+            BlockId::Number(number) => self.get_by_number(number),
+            BlockId::Latest => self.get_by_number(self.max_number?),
+            BlockId::Earliest => self.get_by_number(self.min_number?),
+        }
+    }
+
     pub fn get_by_timestamp(&self, timestamp: u64) -> Vec<&BlockResult> {
         match self.by_timestamp.get(&timestamp) {
             Some(ids) => ids.iter()
@@ -169,21 +346,128 @@ impl BlockResultIndex {
             None => Vec::new(),
         }
     }
-    
+
     pub fn get_in_timestamp_range(&self, start: u64, end: u64) -> Vec<&BlockResult> {
-        let mut results = Vec::new();
-        
-        for timestamp in start..=end {
-            if let Some(ids) = self.by_timestamp.get(&timestamp) {
-                for id in ids {
-                    if let Some(result) = self.by_id.get(id) {
-                        results.push(result);
-                    }
-                }
+        self.by_timestamp.range(start..=end)
+            .flat_map(|(_, ids)| ids)
+            .filter_map(|id| self.by_id.get(id))
+            .collect()
+    }
+
+    /// Returns the most recent block with a timestamp strictly before `ts`.
+    pub fn get_latest_before(&self, ts: u64) -> Option<&BlockResult> {
+        let (_, ids) = self.by_timestamp.range(..ts).next_back()?;
+        ids.first().and_then(|id| self.by_id.get(id))
+    }
+
+    /// Returns the earliest block with a timestamp at or after `ts`.
+    pub fn get_first_after(&self, ts: u64) -> Option<&BlockResult> {
+        let (_, ids) = self.by_timestamp.range(ts..).next()?;
+        ids.first().and_then(|id| self.by_id.get(id))
+    }
+
+    /// Computes the route between two blocks by hash, walking `parent_hash`
+    /// links until the branches meet. Returns `None` if either hash is
+    /// unknown or a parent link is missing before the branches converge.
+    pub fn tree_route(&self, from: &str, to: &str) -> Option<TreeRoute> {
+        let mut from_block = self.get_by_hash(from)?;
+        let mut to_block = self.get_by_hash(to)?;
+
+        if from == to {
+            return Some(TreeRoute {
+                ancestor: from.to_string(),
+                retracted: Vec::new(),
+                enacted: Vec::new(),
+            });
+        }
+
+        let mut retracted = Vec::new();
+        let mut enacted = Vec::new();
+
+        // Walk the higher branch back until both sides are at equal height.
+        while from_block.number > to_block.number {
+            retracted.push(from_block.hash.clone());
+            from_block = self.get_by_hash(&from_block.parent_hash)?;
+        }
+        while to_block.number > from_block.number {
+            enacted.push(to_block.hash.clone());
+            to_block = self.get_by_hash(&to_block.parent_hash)?;
+        }
+
+        // Advance both pointers in lockstep until the branches converge.
+        while from_block.hash != to_block.hash {
+            retracted.push(from_block.hash.clone());
+            enacted.push(to_block.hash.clone());
+            from_block = self.get_by_hash(&from_block.parent_hash)?;
+            to_block = self.get_by_hash(&to_block.parent_hash)?;
+        }
+
+        enacted.reverse();
+
+        Some(TreeRoute {
+            ancestor: from_block.hash.clone(),
+            retracted,
+            enacted,
+        })
+    }
+
+    /// Inserts or replaces a block, keeping `by_id`, `by_hash`, `by_number`,
+    /// `by_timestamp` and the bloom index mutually consistent. If a block
+    /// with the same id already exists (e.g. its hash changed), its stale
+    /// entries are removed first.
+    pub fn insert(&mut self, result: BlockResult) {
+        self.remove(&result.id);
+
+        if let Some(existing_id) = self.by_hash.get(&result.hash).cloned() {
+            log::warn!(
+                "hash {} already indexed under block {}, replacing with {}",
+                result.hash, existing_id, result.id
+            );
+            // Evict the old id entirely so it doesn't linger in by_id (and
+            // other sub-indices) unreachable via its now-stolen hash.
+            self.remove(&existing_id);
+        }
+
+        self.by_hash.insert(result.hash.clone(), result.id.clone());
+        self.by_timestamp.entry(result.timestamp)
+            .or_insert_with(Vec::new)
+            .push(result.id.clone());
+        self.by_number.insert(result.number, result.id.clone());
+        self.min_number = Some(self.min_number.map_or(result.number, |min| min.min(result.number)));
+        self.max_number = Some(self.max_number.map_or(result.number, |max| max.max(result.number)));
+        self.blooms.insert(result.id.clone(), Self::bloom_for(&result));
+        self.by_id.insert(result.id.clone(), result);
+    }
+
+    /// Removes a block by id, surgically unwinding its entry from every
+    /// sub-index. The `by_timestamp` bucket is dropped entirely once it goes
+    /// empty so range scans never trip over empty vectors.
+    pub fn remove(&mut self, id: &str) -> Option<BlockResult> {
+        let result = self.by_id.remove(id)?;
+
+        // Only drop hash/height entries that still point at this block; a
+        // later block may have taken over the same height.
+        if self.by_hash.get(&result.hash).map(String::as_str) == Some(id) {
+            self.by_hash.remove(&result.hash);
+        }
+        if self.by_number.get(&result.number).map(String::as_str) == Some(id) {
+            self.by_number.remove(&result.number);
+        }
+        self.blooms.remove(id);
+
+        if let Some(ids) = self.by_timestamp.get_mut(&result.timestamp) {
+            ids.retain(|existing| existing != id);
+            if ids.is_empty() {
+                self.by_timestamp.remove(&result.timestamp);
             }
         }
-        
-        results
+
+        if self.min_number == Some(result.number) || self.max_number == Some(result.number) {
+            self.min_number = self.by_number.keys().min().copied();
+            self.max_number = self.by_number.keys().max().copied();
+        }
+
+        Some(result)
     }
 }
 
@@ -197,6 +481,8 @@ mod tests {
             BlockResult {
                 id: "block1".to_string(),
                 hash: "hash1".to_string(),
+                parent_hash: String::new(),
+                number: 100,
                 timestamp: 100,
                 data: vec![1, 2, 3],
                 metadata: None,
@@ -204,6 +490,8 @@ mod tests {
             BlockResult {
                 id: "block2".to_string(),
                 hash: "hash2".to_string(),
+                parent_hash: String::new(),
+                number: 200,
                 timestamp: 200,
                 data: vec![4, 5, 6],
                 metadata: None,
@@ -223,6 +511,8 @@ mod tests {
             BlockResult {
                 id: "block1".to_string(),
                 hash: "hash1".to_string(),
+                parent_hash: String::new(),
+                number: 100,
                 timestamp: 100,
                 data: vec![1, 2, 3],
                 metadata: None,
@@ -230,6 +520,8 @@ mod tests {
             BlockResult {
                 id: "block2".to_string(),
                 hash: "hash2".to_string(),
+                parent_hash: String::new(),
+                number: 100,
                 timestamp: 100, // Same timestamp as block1
                 data: vec![4, 5, 6],
                 metadata: None,
@@ -237,6 +529,8 @@ mod tests {
             BlockResult {
                 id: "block3".to_string(),
                 hash: "hash3".to_string(),
+                parent_hash: String::new(),
+                number: 200,
                 timestamp: 200,
                 data: vec![7, 8, 9],
                 metadata: None,
@@ -244,9 +538,128 @@ mod tests {
         ];
         
         let groups = group_results_by(&results, |r| r.timestamp);
-        
+
         assert_eq!(groups.len(), 2); // Two unique timestamps
         assert_eq!(groups.get(&100).unwrap().len(), 2); // Two blocks with timestamp 100
         assert_eq!(groups.get(&200).unwrap().len(), 1); // One block with timestamp 200
     }
+
+    fn sample_block(id: &str, hash: &str, number: u64, timestamp: u64) -> BlockResult {
+        BlockResult {
+            id: id.to_string(),
+            hash: hash.to_string(),
+            parent_hash: String::new(),
+            number,
+            timestamp,
+            data: Vec::new(),
+            metadata: None,
+        }
+    }
+
+    fn sample_block_with_author(id: &str, hash: &str, number: u64, author: &str) -> BlockResult {
+        let mut block = sample_block(id, hash, number, number);
+        block.metadata = Some(BlockMetadata {
+            author: author.to_string(),
+            version: "v1".to_string(),
+            transactions_count: 0,
+        });
+        block
+    }
+
+    #[test]
+    fn test_blocks_matching_finds_author_and_rejects_bloom_false_positives() {
+        let index = BlockResultIndex::new(vec![
+            sample_block_with_author("block1", "hash1", 1, "alice"),
+            sample_block_with_author("block2", "hash2", 2, "bob"),
+            sample_block("block3", "hash3", 3, 3), // no metadata at all
+        ]);
+
+        let matches = index.blocks_matching("alice");
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].id, "block1");
+
+        // "carol" never appears in any block's metadata, so even if her bloom
+        // bits happen to collide with an indexed block's, the exact-match
+        // confirmation must reject it rather than returning a false positive.
+        assert!(index.blocks_matching("carol").is_empty());
+    }
+
+    fn sample_block_with_parent(id: &str, hash: &str, parent_hash: &str, number: u64) -> BlockResult {
+        let mut block = sample_block(id, hash, number, number);
+        block.parent_hash = parent_hash.to_string();
+        block
+    }
+
+    #[test]
+    fn test_tree_route_identical_endpoints_is_a_no_op() {
+        let index = BlockResultIndex::new(vec![sample_block_with_parent("b1", "h1", "", 1)]);
+
+        let route = index.tree_route("h1", "h1").expect("route should resolve");
+
+        assert_eq!(route.ancestor, "h1");
+        assert!(route.retracted.is_empty());
+        assert!(route.enacted.is_empty());
+    }
+
+    #[test]
+    fn test_tree_route_across_diverging_branches() {
+        // genesis -> a1 -> a2 (the "from" branch)
+        //        -> b1 -> b2 -> b3 (the "to" branch)
+        let index = BlockResultIndex::new(vec![
+            sample_block_with_parent("genesis", "h_genesis", "", 0),
+            sample_block_with_parent("a1", "h_a1", "h_genesis", 1),
+            sample_block_with_parent("a2", "h_a2", "h_a1", 2),
+            sample_block_with_parent("b1", "h_b1", "h_genesis", 1),
+            sample_block_with_parent("b2", "h_b2", "h_b1", 2),
+            sample_block_with_parent("b3", "h_b3", "h_b2", 3),
+        ]);
+
+        let route = index.tree_route("h_a2", "h_b3").expect("route should resolve");
+
+        assert_eq!(route.ancestor, "h_genesis");
+        assert_eq!(route.retracted, vec!["h_a2".to_string(), "h_a1".to_string()]);
+        assert_eq!(
+            route.enacted,
+            vec!["h_b1".to_string(), "h_b2".to_string(), "h_b3".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_tree_route_missing_parent_returns_none() {
+        let index = BlockResultIndex::new(vec![
+            sample_block_with_parent("a1", "h_a1", "h_missing_genesis", 1),
+            sample_block_with_parent("b1", "h_b1", "h_missing_genesis", 1),
+        ]);
+
+        assert!(index.tree_route("h_a1", "h_b1").is_none());
+    }
+
+    #[test]
+    fn test_index_insert_replaces_stale_hash_on_reinsert() {
+        let mut index = BlockResultIndex::new(vec![sample_block("block1", "hash1", 1, 100)]);
+
+        assert_eq!(index.get_by_hash("hash1").unwrap().id, "block1");
+
+        index.insert(sample_block("block1", "hash1-new", 1, 150));
+
+        assert!(index.get_by_hash("hash1").is_none());
+        assert_eq!(index.get_by_hash("hash1-new").unwrap().id, "block1");
+        assert_eq!(index.get_by_id("block1").unwrap().timestamp, 150);
+    }
+
+    #[test]
+    fn test_index_remove_one_of_several_sharing_a_timestamp() {
+        let mut index = BlockResultIndex::new(vec![
+            sample_block("block1", "hash1", 1, 100),
+            sample_block("block2", "hash2", 2, 100),
+        ]);
+
+        let removed = index.remove("block1").expect("block1 should exist");
+
+        assert_eq!(removed.id, "block1");
+        assert!(index.get_by_id("block1").is_none());
+        assert!(index.get_by_hash("hash1").is_none());
+        assert_eq!(index.get_by_timestamp(100).len(), 1);
+        assert_eq!(index.get_by_timestamp(100)[0].id, "block2");
+    }
 }